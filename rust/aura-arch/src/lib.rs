@@ -1,6 +1,10 @@
 //! A layer over the `alpm` library to aid with common tasks.
 
-use alpm::{Alpm, Package, PackageReason};
+use alpm::LogLevel as RawLogLevel;
+use alpm::{Alpm, Package, PackageReason, Pkg, SigLevel};
+use std::collections::HashSet;
+use std::ops::Deref;
+use std::path::PathBuf;
 
 /// The default "root" filepath as required by `alpm`.
 pub const DEFAULT_ROOT: &str = "/";
@@ -11,13 +15,202 @@ pub const DEFAULT_DB: &str = "/var/lib/pacman/";
 #[derive(Debug)]
 pub enum Error {
     Alpm(alpm::Error),
+    /// No package in any registered sync database satisfies the given name
+    /// or version constraint.
+    NotFound(String),
+    /// `pacman.conf` could not be parsed.
+    Conf(pacmanconf::Error),
+}
+
+/// Build an [`Alpm`] handle from the system's real `/etc/pacman.conf`,
+/// registering every configured sync database with its proper [`SigLevel`].
+pub fn from_system() -> Result<Alpm, Error> {
+    let conf = pacmanconf::Config::new().map_err(Error::Conf)?;
+    let alpm = Alpm::new(conf.root_dir.clone(), conf.db_path.clone()).map_err(Error::Alpm)?;
+
+    // A registered db's `SigLevel::USE_DEFAULT` bit is resolved against this
+    // handle-wide default, so it must come from the global `[options]
+    // SigLevel` line, not from each repo's own (usually absent) override.
+    alpm.set_default_siglevel(sig_level_from_conf(&conf.sig_level))
+        .map_err(Error::Alpm)?;
+
+    for repo in &conf.repos {
+        let level = sig_level_from_conf(&repo.sig_level);
+        alpm.register_syncdb(repo.name.clone(), level)
+            .map_err(Error::Alpm)?;
+    }
+
+    Ok(alpm)
+}
+
+/// Build an [`Alpm`] handle from a given root and database path, without
+/// registering any sync databases. Pair with [`DEFAULT_ROOT`] and
+/// [`DEFAULT_DB`] when no `pacman.conf` is available to parse.
+pub fn with_paths(root: &str, db_path: &str) -> Result<Alpm, Error> {
+    Alpm::new(root, db_path).map_err(Error::Alpm)
+}
+
+/// Translate the raw `SigLevel = ...` tokens from `pacman.conf` into the
+/// bitflags `alpm` expects.
+///
+/// libalpm treats `USE_DEFAULT` as an override flag, not a bit to merge in:
+/// a db falls back to the handle-wide default only when *no* level was set
+/// at all. So this only starts from `USE_DEFAULT` when there are no tokens
+/// to parse; an explicit `SigLevel = ...` line replaces it entirely.
+fn sig_level_from_conf(tokens: &[String]) -> SigLevel {
+    if tokens.is_empty() {
+        return SigLevel::USE_DEFAULT;
+    }
+
+    let mut level = SigLevel::empty();
+
+    for tok in tokens {
+        level |= match tok.as_str() {
+            "PackageNever" => SigLevel::empty(),
+            "PackageOptional" => SigLevel::PACKAGE | SigLevel::PACKAGE_OPTIONAL,
+            "PackageRequired" => SigLevel::PACKAGE,
+            "PackageTrustedOnly" => SigLevel::PACKAGE,
+            "PackageTrustAll" => {
+                SigLevel::PACKAGE | SigLevel::PACKAGE_MARGINAL_OK | SigLevel::PACKAGE_UNKNOWN_OK
+            }
+            "DatabaseNever" => SigLevel::empty(),
+            "DatabaseOptional" => SigLevel::DATABASE | SigLevel::DATABASE_OPTIONAL,
+            "DatabaseRequired" => SigLevel::DATABASE,
+            "DatabaseTrustedOnly" => SigLevel::DATABASE,
+            "DatabaseTrustAll" => {
+                SigLevel::DATABASE | SigLevel::DATABASE_MARGINAL_OK | SigLevel::DATABASE_UNKNOWN_OK
+            }
+            _ => SigLevel::empty(),
+        };
+    }
+
+    level
+}
+
+/// Where a [`Package`] should be loaded from.
+#[derive(Debug, Clone)]
+pub enum PackageSource {
+    /// A package already installed, looked up by name in the local database.
+    LocalDb(String),
+    /// A dependency string (e.g. `foo` or `foo>=1.2`) resolved against every
+    /// registered sync database.
+    SyncDb(String),
+    /// A package file on disk, e.g. a downloaded `.pkg.tar.zst`.
+    File(PathBuf),
+}
+
+/// Severity of a message emitted by libalpm's internal log stream, ordered
+/// from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Function,
+    Debug,
+    Warning,
+    Error,
+}
+
+impl LogLevel {
+    /// The raw `alpm` log level is a bitflag set, since a single message can
+    /// technically carry more than one bit. Here we only care about the
+    /// single most severe bit that's set.
+    fn from_raw(raw: RawLogLevel) -> Option<LogLevel> {
+        if raw.contains(RawLogLevel::ERROR) {
+            Some(LogLevel::Error)
+        } else if raw.contains(RawLogLevel::WARNING) {
+            Some(LogLevel::Warning)
+        } else if raw.contains(RawLogLevel::DEBUG) {
+            Some(LogLevel::Debug)
+        } else if raw.contains(RawLogLevel::FUNCTION) {
+            Some(LogLevel::Function)
+        } else {
+            None
+        }
+    }
+}
+
+/// Register a callback that observes libalpm's internal log stream, only
+/// invoking `f` for messages at or above `min_level`.
+pub fn set_log_callback<F>(alpm: &mut Alpm, min_level: LogLevel, mut f: F)
+where
+    F: FnMut(LogLevel, &str) + 'static,
+{
+    alpm.set_log_cb((), move |raw, msg, _| {
+        if let Some(level) = LogLevel::from_raw(raw) {
+            if level >= min_level {
+                f(level, msg);
+            }
+        }
+    });
+}
+
+/// The architectures the handle is configured to accept (e.g. `x86_64` and
+/// `any`). Pacman 6 replaced the single `Architecture` setting with a list to
+/// support multilib-style setups.
+pub fn architectures(alpm: &Alpm) -> Vec<String> {
+    alpm.architectures().iter().map(String::from).collect()
+}
+
+/// Whether `pkg` is installable given the handle's configured architectures:
+/// true if its own architecture is `any`, or is one of `archs`.
+pub fn is_installable(pkg: &Package, archs: &[String]) -> bool {
+    match pkg.arch() {
+        Some("any") => true,
+        Some(arch) => archs.iter().any(|a| a == arch),
+        None => false,
+    }
+}
+
+/// A package resolved via [`load`].
+///
+/// A `LocalDb`/`SyncDb` lookup hands back a reference into a database the
+/// handle already owns, while a `File` load hands back an owned package
+/// that's freed when it goes out of scope. These have different ownership
+/// stories, so unlike [`Package`] itself this can't be a single by-value
+/// type — callers that just want to read fields can go through [`Deref`].
+pub enum LoadedPackage<'a> {
+    Db(&'a Package),
+    File(alpm::LoadedPackage<'a>),
+}
+
+impl<'a> Deref for LoadedPackage<'a> {
+    type Target = Pkg;
+
+    fn deref(&self) -> &Pkg {
+        match self {
+            LoadedPackage::Db(pkg) => pkg,
+            LoadedPackage::File(pkg) => pkg,
+        }
+    }
+}
+
+/// Resolve a [`PackageSource`] into an actual package, regardless of whether
+/// it lives in the installed DB, a sync repo, or on disk.
+pub fn load(alpm: &Alpm, src: PackageSource, level: SigLevel) -> Result<LoadedPackage<'_>, Error> {
+    match src {
+        PackageSource::LocalDb(name) => alpm
+            .localdb()
+            .pkg(name)
+            .map(LoadedPackage::Db)
+            .map_err(Error::Alpm),
+        PackageSource::SyncDb(dep) => alpm
+            .syncdbs()
+            .find_satisfier(dep.clone())
+            .map(LoadedPackage::Db)
+            .ok_or(Error::NotFound(dep)),
+        PackageSource::File(path) => {
+            let path = path.to_string_lossy().into_owned();
+            alpm.pkg_load(path, true, level)
+                .map(LoadedPackage::File)
+                .map_err(Error::Alpm)
+        }
+    }
 }
 
 /// All orphaned packages.
 ///
 /// An orphan is a package that was installed as a dependency, but whose parent
 /// package is no longer installed.
-pub fn orphans(alpm: &Alpm) -> Vec<Package> {
+pub fn orphans(alpm: &Alpm) -> Vec<&Package> {
     alpm.localdb()
         .pkgs()
         .iter()
@@ -28,3 +221,123 @@ pub fn orphans(alpm: &Alpm) -> Vec<Package> {
         })
         .collect()
 }
+
+/// All orphaned packages, including those that only become orphaned once their
+/// dependents are themselves removed.
+///
+/// [`orphans`] only reports packages that are *currently* unneeded. But
+/// removing those can leave their own dependencies without a parent in turn,
+/// cascading down a whole chain (`a` depends on `b` depends on `c`, etc.).
+/// This repeatedly expands the removal set until a fixpoint is reached, and
+/// returns it in a safe removal order (leaves first).
+pub fn orphans_transitive(alpm: &Alpm) -> Vec<&Package> {
+    let db = alpm.localdb();
+    let mut removal: Vec<&Package> = orphans(alpm);
+    let mut marked: HashSet<String> = removal.iter().map(|p| p.name().to_string()).collect();
+
+    loop {
+        let mut grew = false;
+
+        for p in db.pkgs().iter() {
+            if p.reason() != PackageReason::Depend || marked.contains(p.name()) {
+                continue;
+            }
+
+            let required_by_gone = p.required_by().iter().all(|r| marked.contains(r));
+            let optional_for_gone = p.optional_for().iter().all(|o| marked.contains(o));
+
+            if required_by_gone && optional_for_gone {
+                marked.insert(p.name().to_string());
+                removal.push(p);
+                grew = true;
+            }
+        }
+
+        if !grew {
+            return removal;
+        }
+    }
+}
+
+/// A node in the tree produced by [`reverse_dep_tree`]: either the root package
+/// itself (the top node, at depth 0 with no parent), or a package that
+/// (directly or indirectly) depends on it.
+#[derive(Debug)]
+pub struct ReverseDepNode<'a> {
+    pub pkg: &'a Package,
+    /// How many hops away from the root package this dependent sits.
+    pub depth: usize,
+    /// The name of the package that pulled this one in, if any.
+    pub parent: Option<String>,
+    pub children: Vec<ReverseDepNode<'a>>,
+}
+
+/// Every installed package that (directly or indirectly) depends on `pkg`,
+/// flattened into a single list.
+pub fn reverse_deps<'a>(alpm: &'a Alpm, pkg: &Package) -> Vec<&'a Package> {
+    let db = alpm.localdb();
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    collect_reverse_deps(db, pkg, &mut seen, &mut out);
+    out
+}
+
+fn collect_reverse_deps<'a>(
+    db: &'a alpm::Db,
+    pkg: &Package,
+    seen: &mut HashSet<String>,
+    out: &mut Vec<&'a Package>,
+) {
+    for name in pkg.required_by().iter() {
+        if seen.insert(name.to_string()) {
+            if let Ok(dependent) = db.pkg(name.to_string()) {
+                out.push(dependent);
+                collect_reverse_deps(db, dependent, seen, out);
+            }
+        }
+    }
+}
+
+/// Walk `pkg`'s `required_by()` transitively and build a tree of everything
+/// that depends on it, so a caller can render "what breaks if I remove this"
+/// before a removal.
+///
+/// A cyclic `required_by` chain would otherwise recurse unbounded, so this
+/// tracks the current path's ancestors and stops descending once one repeats.
+/// Unlike [`reverse_deps`], that set is scoped per-branch rather than shared
+/// across the whole walk: a package reachable from more than one parent (e.g.
+/// a widely-depended package like `glibc`) is not a cycle, and should still
+/// show up under every legitimate parent.
+pub fn reverse_dep_tree<'a>(alpm: &'a Alpm, pkg: &'a Package) -> ReverseDepNode<'a> {
+    let mut ancestors = HashSet::new();
+    ancestors.insert(pkg.name().to_string());
+    reverse_dep_tree_at(alpm, pkg, 0, None, ancestors)
+}
+
+fn reverse_dep_tree_at<'a>(
+    alpm: &'a Alpm,
+    pkg: &'a Package,
+    depth: usize,
+    parent: Option<&str>,
+    ancestors: HashSet<String>,
+) -> ReverseDepNode<'a> {
+    let db = alpm.localdb();
+    let mut children = Vec::new();
+
+    for name in pkg.required_by().iter() {
+        if !ancestors.contains(name) {
+            if let Ok(child) = db.pkg(name.to_string()) {
+                let mut ancestors = ancestors.clone();
+                ancestors.insert(name.to_string());
+                children.push(reverse_dep_tree_at(alpm, child, depth + 1, Some(pkg.name()), ancestors));
+            }
+        }
+    }
+
+    ReverseDepNode {
+        pkg,
+        depth,
+        parent: parent.map(String::from),
+        children,
+    }
+}